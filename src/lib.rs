@@ -0,0 +1,10 @@
+mod list;
+mod query_ast;
+mod query_builder;
+mod query_decomposer;
+#[cfg(test)]
+mod test_util;
+
+pub use query_ast::{QueryAst, QueryAstError};
+pub use query_builder::{ClauseBuilder, QueryBuilder};
+pub use query_decomposer::{decompose, fingerprint, QueryDecomposer};
@@ -26,6 +26,43 @@ impl<'a, T> List<'a, T> {
         self.items.push(item)
     }
 
+    #[cfg(test)]
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.items.pop()
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+        let offset = self.offset;
+        let mut i = 0;
+        self.items.retain(|item| {
+            let keep = i < offset || f(item);
+            i += 1;
+            keep
+        })
+    }
+
+    #[cfg(test)]
+    pub fn dedup_by(&mut self, mut same_bucket: impl FnMut(&mut T, &mut T) -> bool) {
+        let items = &mut self.items[self.offset..];
+        if items.is_empty() {
+            return;
+        }
+
+        let mut retained = 1;
+        for read in 1..items.len() {
+            let (kept, rest) = items.split_at_mut(read);
+            if !same_bucket(&mut rest[0], &mut kept[retained - 1]) {
+                items.swap(retained, read);
+                retained += 1;
+            }
+        }
+
+        self.items.truncate(self.offset + retained)
+    }
+
     pub fn drain(&mut self, range: impl RangeBounds<usize>) -> impl Iterator<Item = T> + '_ {
         self.items.drain((
             match range.start_bound() {
@@ -95,3 +132,44 @@ impl<T> Extend<T> for List<'_, T> {
         self.items.extend(iter);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+
+    #[test]
+    fn pop_stops_at_offset() {
+        let mut items = vec![1, 2];
+        let mut list = List::new(&mut items);
+        let mut saved = list.saved();
+        saved.push(3);
+
+        assert_eq!(saved.pop(), Some(3));
+        assert_eq!(saved.pop(), None);
+        assert_eq!(items, vec![1, 2]);
+    }
+
+    #[test]
+    fn retain_keeps_items_before_offset() {
+        let mut items = vec![1, 2, 3];
+        let mut list = List::new(&mut items);
+        let mut saved = list.saved();
+        saved.extend([4, 5, 6]);
+
+        saved.retain(|&item| item % 2 == 0);
+        assert_eq!(saved.len(), 2);
+        assert_eq!(items, vec![1, 2, 3, 4, 6]);
+    }
+
+    #[test]
+    fn dedup_by_collapses_neighbours_after_offset() {
+        let mut items = vec![1, 1];
+        let mut list = List::new(&mut items);
+        let mut saved = list.saved();
+        saved.extend([1, 2, 2, 3, 2, 2]);
+
+        saved.dedup_by(|a, b| a == b);
+        assert_eq!(saved.len(), 4);
+        assert_eq!(items, vec![1, 1, 1, 2, 3, 2]);
+    }
+}
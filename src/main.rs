@@ -1,29 +1,53 @@
-mod list;
-mod query_decomposer;
+use std::{env, error::Error, fs};
 
-pub use query_decomposer::QueryDecomposer;
+use sentry::{QueryBuilder, QueryDecomposer};
+use serde_json::json;
 use tantivy::{
-    query::{BooleanQuery, Occur, Query, QueryClone, TermQuery},
-    schema::{IndexRecordOption, Schema, TEXT},
-    Term,
+    query::{Query, QueryClone, QueryParser},
+    schema::{FieldType, Schema, TEXT},
+    tokenizer::TokenizerManager,
 };
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<_> = env::args().skip(1).collect();
+    match &args[..] {
+        [] => benchmark(),
+        [command, schema_path, query] if command == "decompose" => {
+            print_decomposition(schema_path, query)?
+        }
+        _ => return Err("usage: sentry [decompose <schema.json> <query>]".into()),
+    }
+    Ok(())
+}
+
+fn print_decomposition(schema_path: &str, query: &str) -> Result<(), Box<dyn Error>> {
+    let schema: Schema = serde_json::from_str(&fs::read_to_string(schema_path)?)?;
+    let default_fields = schema
+        .fields()
+        .filter(|(_, entry)| entry.is_indexed() && matches!(entry.field_type(), FieldType::Str(_)))
+        .map(|(field, _)| field)
+        .collect();
+    let query =
+        QueryParser::new(schema, default_fields, TokenizerManager::default()).parse_query(query)?;
+
+    let subqueries: Vec<_> = sentry::decompose(query.as_ref())
+        .iter()
+        .map(|subquery| format!("{subquery:?}"))
+        .collect();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&json!({ "subqueries": subqueries }))?
+    );
+    Ok(())
+}
+
+fn benchmark() {
     let mut schema_builder = Schema::builder();
     let title = schema_builder.add_text_field("title", TEXT);
-    let girl_term_query: Box<dyn Query> = Box::new(TermQuery::new(
-        Term::from_field_text(title, "girl"),
-        IndexRecordOption::Basic,
-    ));
-    let diary_term_query: Box<dyn Query> = Box::new(TermQuery::new(
-        Term::from_field_text(title, "diary"),
-        IndexRecordOption::Basic,
-    ));
-    let queries_with_occurs1 = vec![
-        (Occur::Must, diary_term_query),
-        (Occur::MustNot, girl_term_query),
-    ];
-    let diary_must_and_girl_mustnot = Box::new(BooleanQuery::new(queries_with_occurs1));
+    let diary_must_and_girl_mustnot = QueryBuilder::term(title, "diary")
+        .and_not()
+        .term(title, "girl")
+        .build();
 
     let mut all_subqueries = Vec::<Box<dyn Query>>::new();
 
@@ -0,0 +1,247 @@
+use std::{error::Error, fmt};
+
+use serde::{Deserialize, Serialize};
+use tantivy::{
+    query::{BooleanQuery, BoostQuery, Occur, PhraseQuery, Query, TermQuery},
+    schema::{Field, FieldType, IndexRecordOption, Schema},
+    Score, Term,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QueryAst {
+    Term { field: String, value: String },
+    Phrase { field: String, values: Vec<String> },
+    And { clauses: Vec<QueryAst> },
+    Or { clauses: Vec<QueryAst> },
+    Not { clause: Box<QueryAst> },
+    Boost { clause: Box<QueryAst>, boost: Score },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryAstError {
+    UnknownField(String),
+    NonTextField(String),
+    UnindexedField(String),
+    EmptyPhrase,
+    EmptyClauses,
+    UnboundNegation,
+}
+
+impl fmt::Display for QueryAstError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownField(field) => write!(f, "unknown field `{field}`"),
+            Self::NonTextField(field) => write!(f, "field `{field}` is not a text field"),
+            Self::UnindexedField(field) => write!(f, "field `{field}` is not indexed"),
+            Self::EmptyPhrase => write!(f, "phrase has no values"),
+            Self::EmptyClauses => write!(f, "conjunction or disjunction has no clauses"),
+            Self::UnboundNegation => write!(
+                f,
+                "negation is not part of a conjunction with a positive clause"
+            ),
+        }
+    }
+}
+
+impl Error for QueryAstError {}
+
+impl QueryAst {
+    pub fn to_query(&self, schema: &Schema) -> Result<Box<dyn Query>, QueryAstError> {
+        match self {
+            Self::Term { field, value } => Ok(term_query(text_field(schema, field)?, value)),
+            Self::Phrase { field, values } => {
+                let field = text_field(schema, field)?;
+                match &values[..] {
+                    [] => Err(QueryAstError::EmptyPhrase),
+                    [value] => Ok(term_query(field, value)),
+                    _ => Ok(Box::new(PhraseQuery::new(
+                        values
+                            .iter()
+                            .map(|value| Term::from_field_text(field, value))
+                            .collect(),
+                    ))),
+                }
+            }
+            Self::And { clauses } => {
+                if clauses.is_empty() {
+                    return Err(QueryAstError::EmptyClauses);
+                }
+
+                let mut mandatory_clauses = Vec::new();
+                let mut exclusion_clauses = Vec::new();
+                for clause in clauses {
+                    match clause {
+                        Self::Not { clause } => exclusion_clauses.push(clause.to_query(schema)?),
+                        clause => mandatory_clauses.push(clause.to_query(schema)?),
+                    }
+                }
+
+                if mandatory_clauses.is_empty() {
+                    return Err(QueryAstError::UnboundNegation);
+                }
+                if mandatory_clauses.len() == 1 && exclusion_clauses.is_empty() {
+                    return Ok(mandatory_clauses.remove(0));
+                }
+
+                Ok(Box::new(BooleanQuery::new(
+                    mandatory_clauses
+                        .into_iter()
+                        .map(|clause| (Occur::Must, clause))
+                        .chain(
+                            exclusion_clauses
+                                .into_iter()
+                                .map(|clause| (Occur::MustNot, clause)),
+                        )
+                        .collect(),
+                )))
+            }
+            Self::Or { clauses } => {
+                let mut disjuncts = clauses
+                    .iter()
+                    .map(|clause| clause.to_query(schema))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                match disjuncts.len() {
+                    0 => Err(QueryAstError::EmptyClauses),
+                    1 => Ok(disjuncts.remove(0)),
+                    _ => Ok(Box::new(BooleanQuery::new(
+                        disjuncts
+                            .into_iter()
+                            .map(|disjunct| (Occur::Should, disjunct))
+                            .collect(),
+                    ))),
+                }
+            }
+            Self::Not { .. } => Err(QueryAstError::UnboundNegation),
+            Self::Boost { clause, boost } => {
+                Ok(Box::new(BoostQuery::new(clause.to_query(schema)?, *boost)))
+            }
+        }
+    }
+}
+
+fn text_field(schema: &Schema, name: &str) -> Result<Field, QueryAstError> {
+    let field = schema
+        .get_field(name)
+        .ok_or_else(|| QueryAstError::UnknownField(name.to_string()))?;
+    let entry = schema.get_field_entry(field);
+
+    if !matches!(entry.field_type(), FieldType::Str(_)) {
+        return Err(QueryAstError::NonTextField(name.to_string()));
+    }
+    if !entry.is_indexed() {
+        return Err(QueryAstError::UnindexedField(name.to_string()));
+    }
+    Ok(field)
+}
+
+fn term_query(field: Field, text: &str) -> Box<dyn Query> {
+    Box::new(TermQuery::new(
+        Term::from_field_text(field, text),
+        IndexRecordOption::Basic,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::{
+        query::{BooleanQuery, BoostQuery, Occur, PhraseQuery},
+        schema::{Schema, INDEXED, STORED, STRING, TEXT},
+        Term,
+    };
+
+    use super::{term_query, QueryAst, QueryAstError};
+
+    fn schema() -> Schema {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("body", TEXT);
+        schema_builder.add_text_field("tag", STRING);
+        schema_builder.add_text_field("summary", STORED);
+        schema_builder.add_u64_field("views", INDEXED);
+        schema_builder.build()
+    }
+
+    fn parse(json: &str) -> QueryAst {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn converts_json_ast() {
+        let schema = schema();
+        let body = schema.get_field("body").unwrap();
+        let tag = schema.get_field("tag").unwrap();
+
+        let ast = parse(
+            r#"{
+                "type": "and",
+                "clauses": [
+                    {"type": "not", "clause": {"type": "term", "field": "body", "value": "mayor"}},
+                    {"type": "or", "clauses": [
+                        {"type": "term", "field": "body", "value": "bloomberg"},
+                        {"type": "phrase", "field": "body", "values": ["new", "york"]}
+                    ]},
+                    {"type": "boost", "boost": 2.0, "clause": {"type": "term", "field": "tag", "value": "News"}}
+                ]
+            }"#,
+        );
+        let new_york = PhraseQuery::new(vec![
+            Term::from_field_text(body, "new"),
+            Term::from_field_text(body, "york"),
+        ]);
+        let expected = BooleanQuery::new(vec![
+            (
+                Occur::Must,
+                Box::new(BooleanQuery::new(vec![
+                    (Occur::Should, term_query(body, "bloomberg")),
+                    (Occur::Should, Box::new(new_york)),
+                ])),
+            ),
+            (
+                Occur::Must,
+                Box::new(BoostQuery::new(term_query(tag, "News"), 2.0)),
+            ),
+            (Occur::MustNot, term_query(body, "mayor")),
+        ]);
+
+        assert_eq!(
+            format!("{:?}", ast.to_query(&schema).unwrap()),
+            format!("{expected:?}")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_asts() {
+        let schema = schema();
+        let error = |json: &str| parse(json).to_query(&schema).err();
+
+        assert_eq!(
+            error(r#"{"type": "term", "field": "title", "value": "a"}"#),
+            Some(QueryAstError::UnknownField("title".to_string()))
+        );
+        assert_eq!(
+            error(r#"{"type": "term", "field": "views", "value": "1"}"#),
+            Some(QueryAstError::NonTextField("views".to_string()))
+        );
+        assert_eq!(
+            error(r#"{"type": "term", "field": "summary", "value": "a"}"#),
+            Some(QueryAstError::UnindexedField("summary".to_string()))
+        );
+        assert_eq!(
+            error(r#"{"type": "phrase", "field": "body", "values": []}"#),
+            Some(QueryAstError::EmptyPhrase)
+        );
+        assert_eq!(
+            error(r#"{"type": "or", "clauses": []}"#),
+            Some(QueryAstError::EmptyClauses)
+        );
+        assert_eq!(
+            error(
+                r#"{"type": "and", "clauses": [
+                    {"type": "not", "clause": {"type": "term", "field": "body", "value": "a"}}
+                ]}"#
+            ),
+            Some(QueryAstError::UnboundNegation)
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::{iter, mem};
+
+use tantivy::{
+    query::{BooleanQuery, BoostQuery, Occur, PhraseQuery, Query, TermQuery},
+    schema::{Field, IndexRecordOption},
+    Score, Term,
+};
+
+/// Connectives are applied left to right: `a.or().b.and().c` builds `(a OR b) AND c`.
+pub struct QueryBuilder {
+    clauses: Vec<(Occur, Box<dyn Query>)>,
+}
+
+pub struct ClauseBuilder {
+    builder: QueryBuilder,
+    occur: Occur,
+}
+
+impl QueryBuilder {
+    /// The text is used as-is, not tokenized, so it must equal an indexed token (e.g. lowercased).
+    pub fn term(field: Field, text: &str) -> Self {
+        Self::query(term_query(field, text))
+    }
+
+    /// Like [`QueryBuilder::term`], the words are not tokenized.
+    pub fn phrase(field: Field, first: &str, rest: &[&str]) -> Self {
+        Self::query(phrase_query(field, first, rest))
+    }
+
+    pub fn query(query: Box<dyn Query>) -> Self {
+        Self {
+            clauses: vec![(Occur::Must, query)],
+        }
+    }
+
+    pub fn and(self) -> ClauseBuilder {
+        ClauseBuilder {
+            builder: self,
+            occur: Occur::Must,
+        }
+    }
+
+    pub fn or(self) -> ClauseBuilder {
+        ClauseBuilder {
+            builder: self,
+            occur: Occur::Should,
+        }
+    }
+
+    pub fn and_not(self) -> ClauseBuilder {
+        ClauseBuilder {
+            builder: self,
+            occur: Occur::MustNot,
+        }
+    }
+
+    pub fn boost(self, boost: Score) -> Self {
+        Self::query(Box::new(BoostQuery::new(self.build(), boost)))
+    }
+
+    pub fn build(self) -> Box<dyn Query> {
+        build_clauses(self.clauses)
+    }
+
+    fn push(mut self, occur: Occur, query: Box<dyn Query>) -> Self {
+        let connective = match occur {
+            Occur::Should => Occur::Should,
+            Occur::Must | Occur::MustNot => Occur::Must,
+        };
+
+        if self.clauses.len() == 1 {
+            self.clauses[0].0 = connective;
+        } else if self
+            .clauses
+            .iter()
+            .any(|&(clause_occur, _)| (clause_occur == Occur::Should) != (occur == Occur::Should))
+        {
+            let query = build_clauses(mem::take(&mut self.clauses));
+            self.clauses.push((connective, query));
+        }
+
+        self.clauses.push((occur, query));
+        self
+    }
+}
+
+impl ClauseBuilder {
+    pub fn term(self, field: Field, text: &str) -> QueryBuilder {
+        self.query(term_query(field, text))
+    }
+
+    pub fn phrase(self, field: Field, first: &str, rest: &[&str]) -> QueryBuilder {
+        self.query(phrase_query(field, first, rest))
+    }
+
+    pub fn query(self, query: Box<dyn Query>) -> QueryBuilder {
+        self.builder.push(self.occur, query)
+    }
+}
+
+fn build_clauses(mut clauses: Vec<(Occur, Box<dyn Query>)>) -> Box<dyn Query> {
+    if clauses.len() == 1 {
+        return clauses.pop().unwrap().1;
+    }
+
+    Box::new(BooleanQuery::new(clauses))
+}
+
+fn term_query(field: Field, text: &str) -> Box<dyn Query> {
+    Box::new(TermQuery::new(
+        Term::from_field_text(field, text),
+        IndexRecordOption::Basic,
+    ))
+}
+
+fn phrase_query(field: Field, first: &str, rest: &[&str]) -> Box<dyn Query> {
+    if rest.is_empty() {
+        return term_query(field, first);
+    }
+
+    Box::new(PhraseQuery::new(
+        iter::once(first)
+            .chain(rest.iter().copied())
+            .map(|text| Term::from_field_text(field, text))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::{
+        query::{BooleanQuery, BoostQuery, Occur, PhraseQuery, Query, TermQuery},
+        Term,
+    };
+
+    use super::QueryBuilder;
+    use crate::test_util::title;
+
+    fn text(term: &Term) -> &str {
+        term.as_str().unwrap()
+    }
+
+    fn shape(query: &dyn Query) -> String {
+        if let Some(query) = query.downcast_ref::<TermQuery>() {
+            return text(query.term()).to_string();
+        }
+        if let Some(query) = query.downcast_ref::<PhraseQuery>() {
+            let terms = query.phrase_terms();
+            let texts: Vec<_> = terms.iter().map(text).collect();
+            return format!("\"{}\"", texts.join(" "));
+        }
+        if let Some(query) = query.downcast_ref::<BoostQuery>() {
+            return format!("({})^{}", shape(query.query().as_ref()), query.boost());
+        }
+        let query = query.downcast_ref::<BooleanQuery>().unwrap();
+        let clauses: Vec<_> = query
+            .clauses()
+            .iter()
+            .map(|(occur, clause)| {
+                let prefix = match occur {
+                    Occur::Should => "",
+                    Occur::Must => "+",
+                    Occur::MustNot => "-",
+                };
+                format!("{prefix}{}", shape(clause.as_ref()))
+            })
+            .collect();
+        format!("({})", clauses.join(" "))
+    }
+
+    #[test]
+    fn single_clause_builds_the_clause_itself() {
+        let title = title();
+        assert_eq!(shape(QueryBuilder::term(title, "a").build().as_ref()), "a");
+        assert_eq!(
+            shape(QueryBuilder::term(title, "a").boost(2.0).build().as_ref()),
+            "(a)^2"
+        );
+        assert_eq!(
+            shape(QueryBuilder::phrase(title, "a", &[]).build().as_ref()),
+            "a"
+        );
+        assert_eq!(
+            shape(QueryBuilder::phrase(title, "a", &["b"]).build().as_ref()),
+            "\"a b\""
+        );
+    }
+
+    #[test]
+    fn same_connective_extends_the_clause_list() {
+        let title = title();
+        let query = QueryBuilder::term(title, "a")
+            .and()
+            .term(title, "b")
+            .and_not()
+            .term(title, "c")
+            .build();
+        assert_eq!(shape(query.as_ref()), "(+a +b -c)");
+    }
+
+    #[test]
+    fn changing_connective_groups_the_query_so_far() {
+        let title = title();
+        let query = QueryBuilder::term(title, "a")
+            .or()
+            .term(title, "b")
+            .and()
+            .term(title, "c")
+            .build();
+        assert_eq!(shape(query.as_ref()), "(+(a b) +c)");
+
+        let query = QueryBuilder::term(title, "a")
+            .and_not()
+            .term(title, "b")
+            .or()
+            .term(title, "c")
+            .build();
+        assert_eq!(shape(query.as_ref()), "((+a -b) c)");
+
+        let query = QueryBuilder::term(title, "a")
+            .or()
+            .term(title, "b")
+            .and_not()
+            .term(title, "c")
+            .build();
+        assert_eq!(shape(query.as_ref()), "(+(a b) -c)");
+    }
+}
@@ -1,7 +1,106 @@
-use std::iter;
+use std::{collections::HashSet, iter};
 
 use crate::list::List;
-use tantivy::query::{BooleanQuery, BoostQuery, DisjunctionMaxQuery, Occur, Query, QueryClone};
+use tantivy::query::{
+    BooleanQuery, BoostQuery, DisjunctionMaxQuery, Occur, PhraseQuery, Query, QueryClone, TermQuery,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+pub fn decompose(query: &dyn Query) -> Vec<Box<dyn Query>> {
+    let mut all_subqueries = Vec::new();
+    QueryDecomposer::new(&mut all_subqueries).decompose(query.box_clone());
+    all_subqueries
+}
+
+pub fn fingerprint(query: &dyn Query) -> u64 {
+    let mut keys: Vec<_> = decompose(query)
+        .iter()
+        .map(|subquery| structural_key(subquery.as_ref()))
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+
+    // Queries that decompose to nothing (e.g. pure exclusions) are told apart by their own structure.
+    if keys.is_empty() {
+        let mut key = vec![b'N'];
+        encode_query(query, &mut key);
+        keys.push(key);
+    }
+
+    let mut bytes = Vec::new();
+    encode_keys(keys, &mut bytes);
+
+    // FNV-1a rather than DefaultHasher, whose output may change between Rust releases.
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn structural_key(query: &dyn Query) -> Vec<u8> {
+    let mut key = Vec::new();
+    encode_query(query, &mut key);
+    key
+}
+
+// Clauses of boolean and dismax queries are sorted, so clause order never affects the key.
+fn encode_query(query: &dyn Query, key: &mut Vec<u8>) {
+    if let Some(query) = query.downcast_ref::<TermQuery>() {
+        key.push(b'T');
+        encode_bytes(query.term().as_slice(), key);
+    } else if let Some(query) = query.downcast_ref::<PhraseQuery>() {
+        // Slop and term offsets have no getters, but both are part of the debug output.
+        key.push(b'P');
+        encode_bytes(format!("{query:?}").as_bytes(), key);
+    } else if let Some(query) = query.downcast_ref::<BooleanQuery>() {
+        key.push(b'B');
+        let mut clause_keys: Vec<_> = query
+            .clauses()
+            .iter()
+            .map(|(occur, clause)| {
+                let mut clause_key = vec![match occur {
+                    Occur::Should => b'S',
+                    Occur::Must => b'M',
+                    Occur::MustNot => b'X',
+                }];
+                encode_query(clause.as_ref(), &mut clause_key);
+                clause_key
+            })
+            .collect();
+        clause_keys.sort_unstable();
+        encode_keys(clause_keys, key);
+    } else if let Some(query) = query.downcast_ref::<BoostQuery>() {
+        key.push(b'^');
+        key.extend(query.boost().to_bits().to_le_bytes());
+        encode_query(query.query().as_ref(), key);
+    } else if let Some(query) = query.downcast_ref::<DisjunctionMaxQuery>() {
+        key.push(b'D');
+        let mut disjunct_keys: Vec<_> = query
+            .disjuncts()
+            .iter()
+            .map(|disjunct| structural_key(disjunct.as_ref()))
+            .collect();
+        disjunct_keys.sort_unstable();
+        encode_keys(disjunct_keys, key);
+    } else {
+        // No accessors to walk other query types with, so their debug output has to do.
+        key.push(b'?');
+        encode_bytes(format!("{query:?}").as_bytes(), key);
+    }
+}
+
+fn encode_keys(keys: Vec<Vec<u8>>, bytes: &mut Vec<u8>) {
+    bytes.extend((keys.len() as u32).to_le_bytes());
+    for key in keys {
+        encode_bytes(&key, bytes);
+    }
+}
+
+fn encode_bytes(value: &[u8], bytes: &mut Vec<u8>) {
+    bytes.extend((value.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(value);
+}
 
 pub struct QueryDecomposer<'a> {
     all_subqueries: List<'a, Box<dyn Query>>,
@@ -16,7 +115,14 @@ impl<'a> QueryDecomposer<'a> {
         Self { all_subqueries }
     }
 
-    pub fn decompose(&mut self, query: Box<dyn Query>) {
+    /// Returns how many structurally duplicate subqueries were suppressed.
+    pub fn decompose(&mut self, query: Box<dyn Query>) -> usize {
+        let mut decomposer = QueryDecomposer::from_list(self.all_subqueries.saved());
+        decomposer.decompose_query(query);
+        decomposer.dedup()
+    }
+
+    fn decompose_query(&mut self, query: Box<dyn Query>) {
         let mut decomposer = QueryDecomposer::from_list(self.all_subqueries.saved());
 
         let query = match query.downcast::<BooleanQuery>() {
@@ -25,18 +131,30 @@ impl<'a> QueryDecomposer<'a> {
         };
 
         let query = match query.downcast::<BoostQuery>() {
-            Ok(query) => return decomposer.decompose_boost(query),
+            Ok(query) => return decomposer.decompose_boost(&query),
             Err(query) => query,
         };
 
         let query = match query.downcast::<DisjunctionMaxQuery>() {
-            Ok(query) => return decomposer.decompose_disjunction_max(query),
+            Ok(query) => return decomposer.decompose_disjunction_max(&query),
             Err(query) => query,
         };
 
         self.all_subqueries.push(query);
     }
 
+    fn dedup(&mut self) -> usize {
+        let len = self.all_subqueries.len();
+        if len < 2 {
+            return 0;
+        }
+
+        let mut seen = HashSet::new();
+        self.all_subqueries
+            .retain(|subquery| seen.insert(structural_key(subquery.as_ref())));
+        len - self.all_subqueries.len()
+    }
+
     fn decompose_boolean(&mut self, query: Box<BooleanQuery>) {
         let mut mandatory_clauses = Vec::new();
         let mut exclusion_clauses = Vec::new();
@@ -45,7 +163,7 @@ impl<'a> QueryDecomposer<'a> {
             match occur {
                 Occur::Should => {
                     QueryDecomposer::from_list(self.all_subqueries.saved())
-                        .decompose(query.box_clone());
+                        .decompose_query(query.box_clone());
                 }
                 Occur::Must => {
                     mandatory_clauses.push(query);
@@ -65,7 +183,7 @@ impl<'a> QueryDecomposer<'a> {
 
         if let &[mandatory_clause] = &mandatory_clauses[..] {
             QueryDecomposer::from_list(self.all_subqueries.saved())
-                .decompose(mandatory_clause.box_clone());
+                .decompose_query(mandatory_clause.box_clone());
         }
 
         if exclusion_clauses.is_empty() {
@@ -85,20 +203,157 @@ impl<'a> QueryDecomposer<'a> {
         }
     }
 
-    fn decompose_boost(&mut self, query: Box<BoostQuery>) {
+    fn decompose_boost(&mut self, query: &BoostQuery) {
         if query.boost() == 1.0 {
-            return self.decompose(query.query());
+            return self.decompose_query(query.query());
         }
 
-        self.decompose(query.query());
+        self.decompose_query(query.query());
         for subquery in &mut self.all_subqueries {
             *subquery = Box::new(BoostQuery::new(subquery.box_clone(), query.boost()));
         }
     }
 
-    fn decompose_disjunction_max(&mut self, query: Box<DisjunctionMaxQuery>) {
+    fn decompose_disjunction_max(&mut self, query: &DisjunctionMaxQuery) {
         for subquery in query.disjuncts() {
-            self.decompose(subquery.box_clone());
+            self.decompose_query(subquery.box_clone());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tantivy::{
+        query::{BooleanQuery, Occur, PhraseQuery, Query, TermQuery},
+        Term,
+    };
+
+    use super::{decompose, fingerprint, QueryDecomposer};
+    use crate::{query_builder::QueryBuilder, test_util::title};
+
+    #[test]
+    fn decompose_returns_deduplicated_subqueries() {
+        let title = title();
+        let query = BooleanQuery::new(vec![
+            (Occur::Should, QueryBuilder::term(title, "a").build()),
+            (Occur::Should, QueryBuilder::term(title, "a").build()),
+            (Occur::Should, QueryBuilder::term(title, "b").build()),
+        ]);
+
+        assert_eq!(decompose(&query).len(), 2);
+    }
+
+    #[test]
+    fn decompose_suppresses_duplicate_subqueries() {
+        let title = title();
+        let a_and_b = Box::new(BooleanQuery::new(vec![
+            (Occur::Must, QueryBuilder::term(title, "a").build()),
+            (Occur::Must, QueryBuilder::term(title, "b").build()),
+        ]));
+        let query = Box::new(BooleanQuery::new(vec![
+            (Occur::Should, QueryBuilder::term(title, "a").build()),
+            (Occur::Should, QueryBuilder::term(title, "a").build()),
+            (Occur::Should, a_and_b),
+        ]));
+
+        let mut all_subqueries = Vec::new();
+        let suppressed = QueryDecomposer::new(&mut all_subqueries).decompose(query);
+
+        assert_eq!(suppressed, 1);
+        assert_eq!(all_subqueries.len(), 2);
+        assert!(all_subqueries[0].is::<TermQuery>());
+        assert!(all_subqueries[1].is::<BooleanQuery>());
+    }
+
+    #[test]
+    fn decompose_keeps_subqueries_of_earlier_calls() {
+        let title = title();
+        let a_or_b = Box::new(BooleanQuery::new(vec![
+            (Occur::Should, QueryBuilder::term(title, "a").build()),
+            (Occur::Should, QueryBuilder::term(title, "b").build()),
+        ]));
+
+        let mut all_subqueries = Vec::new();
+        let mut query_decomposer = QueryDecomposer::new(&mut all_subqueries);
+        assert_eq!(
+            query_decomposer.decompose(QueryBuilder::term(title, "a").build()),
+            0
+        );
+        assert_eq!(query_decomposer.decompose(a_or_b), 0);
+        assert_eq!(all_subqueries.len(), 3);
+    }
+
+    fn boolean(clauses: Vec<(Occur, &str)>) -> BooleanQuery {
+        let title = title();
+        BooleanQuery::new(
+            clauses
+                .into_iter()
+                .map(|(occur, text)| (occur, QueryBuilder::term(title, text).build()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn fingerprint_is_pinned() {
+        assert_eq!(
+            fingerprint(QueryBuilder::term(title(), "a").build().as_ref()),
+            12741178899279429271
+        );
+    }
+
+    #[test]
+    fn fingerprint_matches_equal_and_reordered_queries() {
+        assert_eq!(
+            fingerprint(QueryBuilder::term(title(), "a").build().as_ref()),
+            fingerprint(QueryBuilder::term(title(), "a").build().as_ref())
+        );
+        assert_eq!(
+            fingerprint(&boolean(vec![(Occur::Must, "a"), (Occur::Must, "b")])),
+            fingerprint(&boolean(vec![(Occur::Must, "b"), (Occur::Must, "a")]))
+        );
+        assert_eq!(
+            fingerprint(&boolean(vec![(Occur::Should, "a"), (Occur::Should, "b")])),
+            fingerprint(&boolean(vec![(Occur::Should, "b"), (Occur::Should, "a")]))
+        );
+    }
+
+    #[test]
+    fn fingerprint_separates_differing_queries() {
+        assert_ne!(
+            fingerprint(QueryBuilder::term(title(), "a").build().as_ref()),
+            fingerprint(QueryBuilder::term(title(), "b").build().as_ref())
+        );
+        assert_ne!(
+            fingerprint(&boolean(vec![(Occur::Must, "a"), (Occur::Must, "b")])),
+            fingerprint(&boolean(vec![(Occur::Must, "a"), (Occur::MustNot, "b")]))
+        );
+        assert_ne!(
+            fingerprint(&boolean(vec![(Occur::MustNot, "a")])),
+            fingerprint(&boolean(vec![(Occur::MustNot, "b")]))
+        );
+        assert_ne!(
+            fingerprint(&boolean(vec![])),
+            fingerprint(&boolean(vec![(Occur::MustNot, "a")]))
+        );
+    }
+
+    #[test]
+    fn phrases_differing_in_slop_stay_distinct() {
+        let title = title();
+        let phrase = |slop| {
+            let mut phrase = PhraseQuery::new(vec![
+                Term::from_field_text(title, "a"),
+                Term::from_field_text(title, "b"),
+            ]);
+            phrase.set_slop(slop);
+            phrase
+        };
+        let query = BooleanQuery::new(vec![
+            (Occur::Should, Box::new(phrase(0)) as Box<dyn Query>),
+            (Occur::Should, Box::new(phrase(3))),
+        ]);
+
+        assert_eq!(decompose(&query).len(), 2);
+        assert_ne!(fingerprint(&phrase(0)), fingerprint(&phrase(3)));
+    }
+}
@@ -0,0 +1,6 @@
+use tantivy::schema::{Field, Schema, TEXT};
+
+pub fn title() -> Field {
+    let mut schema_builder = Schema::builder();
+    schema_builder.add_text_field("title", TEXT)
+}